@@ -1,21 +1,16 @@
 use std::collections::HashMap;
 
+use base64::Engine;
 use lambda_http::{Body, Error, Response, http::HeaderMap, service_fn};
-use serde::{Deserialize, Serialize};
+use prost::Message;
+use proto::NormalizedLog;
+use serde::Deserialize;
+use tracing::{error, info, instrument};
 /*
     we have two possibilties for input:
         txt (so header is plain/text): we treat the body as raw text, and grab tenant from header
         json (so header is application/json):we grab tenant from the body, and parse into the struct.
 */
-#[derive(Serialize)]
-struct NormalizedLog {
-    tenant_id: String,
-    text: String,
-    source: Option<String>,    // e.g. "web", "mobile", "plaintext"
-    timestamp: Option<String>, // may be client provided
-    tags: Option<Vec<String>>,
-    metadata: Option<HashMap<String, String>>,
-}
 #[derive(Deserialize)]
 struct IncomingData {
     tenant_id: String,
@@ -25,13 +20,19 @@ struct IncomingData {
 
 #[tokio::main]
 async fn main() -> Result<(), Error> {
+    tracing_subscriber::fmt()
+        .json()
+        .with_current_span(true)
+        .with_span_list(true)
+        .with_target(false)
+        .init();
+
     lambda_http::run(service_fn(func)).await
 }
 
 // function recieves an event with a firstname field and returns a message to the caller
+#[instrument(skip_all, fields(tenant_id = tracing::field::Empty))]
 async fn func(event: lambda_http::Request) -> Result<Response<Body>, Error> {
-    println!("INGEST: received request");
-
     // grab useful information from the event
     let headers = &event.headers();
 
@@ -43,60 +44,54 @@ async fn func(event: lambda_http::Request) -> Result<Response<Body>, Error> {
     let body_bytes = event.body();
     let body_str = std::str::from_utf8(body_bytes.as_ref())
         .map_err(|e| {
-            eprintln!("INGEST ERROR: Invalid UTF-8 in body: {}", e);
+            error!(error = %e, "invalid UTF-8 in body");
             "Invalid UTF-8 in body"
         })?;
 
-    println!("INGEST: body length = {} bytes", body_str.len());
-
     // handle conversion "overseeing" logic given the content type of the http request
     let normalized = match content_type.and_then(|s| s.to_str().ok()) {
-        Some("text/plain") => {
-            println!("INGEST: handling text/plain");
-            handle_plaintext(&headers, &body_str)
-        }
-        Some("application/json") => {
-            println!("INGEST: handling application/json");
-            handle_json(&body_str)
-        }
+        Some("text/plain") => handle_plaintext(&headers, &body_str),
+        Some("application/json") => handle_json(&body_str),
         Some(other) => {
-            eprintln!("INGEST ERROR: Unsupported content-type: {}", other);
+            error!(content_type = other, "unsupported content-type");
             Err(format!("Unsupported content-type: {}", other).into())
         }
         None => {
-            eprintln!("INGEST ERROR: Missing Content-Type header");
+            error!("missing Content-Type header");
             Err("Missing Content-Type header".into())
         }
     }?; // unwrap the result
 
-    println!("INGEST: normalized tenant_id={}", normalized.tenant_id);
+    tracing::Span::current().record("tenant_id", tracing::field::display(&normalized.tenant_id));
 
-    // serialize to json
-    let message_json = serde_json::to_string(&normalized)
-        .map_err(|e| {
-            eprintln!("INGEST ERROR: Failed to serialize: {}", e);
-            e
-        })?;
+    // encode as protobuf (compact + schema-checked) and base64 it, since SQS
+    // standard queue bodies must be valid UTF-8 text; prefix with
+    // `proto::CONTENT_PREFIX` so the worker can tell this apart from a
+    // legacy JSON body while both are in flight during the migration
+    let message_body = format!(
+        "{}{}",
+        proto::CONTENT_PREFIX,
+        base64::engine::general_purpose::STANDARD.encode(normalized.encode_to_vec())
+    );
 
     // set up sqs integration from env vars
     let config = aws_config::load_defaults(aws_config::BehaviorVersion::latest()).await;
     let sqs_client = aws_sdk_sqs::Client::new(&config);
     let queue_url = std::env::var("QUEUE_URL").expect("QUEUE_URL not set");
 
-    // we send the serialized message to the broker
-    println!("INGEST: sending to SQS queue");
+    // we send the encoded message to the broker
     sqs_client
         .send_message()
         .queue_url(queue_url)
-        .message_body(message_json)
+        .message_body(message_body)
         .send()
         .await
         .map_err(|e| {
-            eprintln!("INGEST ERROR: SQS send failed: {}", e);
+            error!(error = %e, "SQS send failed");
             format!("Failed to send to SQS: {}", e)
         })?;
 
-    println!("INGEST: successfully queued message");
+    info!("successfully queued message");
 
     Ok(Response::builder()
         .status(202)
@@ -107,12 +102,10 @@ async fn func(event: lambda_http::Request) -> Result<Response<Body>, Error> {
 fn handle_json(body: &str) -> Result<NormalizedLog, Error> {
     let incoming: IncomingData = serde_json::from_str(&body)
         .map_err(|e| {
-            eprintln!("INGEST ERROR: Failed to parse JSON body: {}", e);
+            error!(error = %e, "failed to parse JSON body");
             e
         })?;
 
-    println!("INGEST: parsed JSON tenant_id={} log_id={}", incoming.tenant_id, incoming.log_id);
-
     let mut metadata = HashMap::new();
     metadata.insert("log_id".to_string(), incoming.log_id);
 
@@ -121,8 +114,8 @@ fn handle_json(body: &str) -> Result<NormalizedLog, Error> {
         text: incoming.text,
         source: Some("json".to_string()),
         timestamp: None,
-        tags: None,
-        metadata: Some(metadata),
+        tags: Vec::new(),
+        metadata,
     })
 }
 
@@ -131,12 +124,11 @@ fn handle_plaintext(headers: &HeaderMap, body: &str) -> Result<NormalizedLog, Er
         .get("X-Tenant-ID")
         .and_then(|v| v.to_str().ok()) // if header exists and is valid UTF-8
         .ok_or_else(|| {
-            eprintln!("INGEST ERROR: Missing X-Tenant-ID header for plaintext request");
+            error!("missing X-Tenant-ID header for plaintext request");
             "Missing X-Tenant-ID header"
         })?; // converst Option to result, or returns error if None
 
     let log_id = uuid::Uuid::new_v4().to_string();
-    println!("INGEST: parsed plaintext tenant_id={} generated_log_id={}", tenant_id, log_id);
 
     let mut metadata = HashMap::new();
     metadata.insert("log_id".to_string(), log_id);
@@ -146,8 +138,8 @@ fn handle_plaintext(headers: &HeaderMap, body: &str) -> Result<NormalizedLog, Er
         text: body.to_string(),
         source: Some("plaintext".to_string()),
         timestamp: None,
-        tags: None,
-        metadata: Some(metadata),
+        tags: Vec::new(),
+        metadata,
     })
 }
 
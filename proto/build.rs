@@ -0,0 +1,3 @@
+fn main() {
+    prost_build::compile_protos(&["normalized_log.proto"], &["."]).expect("failed to compile normalized_log.proto");
+}
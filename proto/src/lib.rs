@@ -0,0 +1,13 @@
+//! Shared prost-generated wire types for messages passed between the ingest
+//! lambda and the worker over SQS.
+//!
+//! `normalized_log.proto` is the canonical schema; both binaries depend on
+//! this crate instead of hand-declaring their own copy of `NormalizedLog`,
+//! so the two independently-compiled functions can't drift apart.
+
+include!(concat!(env!("OUT_DIR"), "/memory_machines.v1.rs"));
+
+/// Prefixes an SQS message body that carries a protobuf-encoded (base64)
+/// `NormalizedLog`, so the worker can tell it apart from a legacy
+/// JSON-encoded body during the migration window.
+pub const CONTENT_PREFIX: &str = "pb1:";
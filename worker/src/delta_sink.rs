@@ -0,0 +1,111 @@
+//! Delta Lake / Parquet archival sink for `ProcessedLog`s.
+//!
+//! DynamoDB is great for point lookups but expensive for bulk analytics and
+//! long-term retention. This sink buffers a whole SQS batch's
+//! `ProcessedLog`s into a single Arrow record batch and commits it to a
+//! Delta table on S3 in one transaction, partitioned by `tenant_id` and the
+//! UTC day `processed_at` falls on, so downstream Spark/DuckDB jobs can
+//! query history without scanning DynamoDB. Selected via `SINK_MODE` (see
+//! `write_sinks` in `main.rs`).
+
+use std::sync::Arc;
+
+use arrow::array::StringArray;
+use arrow::datatypes::{DataType, Field, Schema};
+use arrow::record_batch::RecordBatch;
+use deltalake::kernel::{DataType as DeltaDataType, PrimitiveType, StructField};
+use deltalake::operations::create::CreateBuilder;
+use deltalake::{DeltaOps, DeltaTable};
+use lambda_runtime::Error;
+use tracing::{info, instrument};
+
+use crate::ProcessedLog;
+
+const PARTITION_COLUMNS: [&str; 2] = ["tenant_id", "ingest_date"];
+
+fn table_uri() -> String {
+    std::env::var("DELTA_TABLE_URI").unwrap_or_else(|_| "s3://processed-logs/delta".to_string())
+}
+
+/// Append a whole batch of processed logs to the Delta table in a single
+/// commit. Partial failure isn't meaningful here (it's one Parquet file /
+/// one `_delta_log` entry for the whole batch), so callers should treat an
+/// `Err` as the whole batch failing.
+#[instrument(skip(logs), fields(batch_size = logs.len()))]
+pub(crate) async fn write_batch(logs: &[ProcessedLog]) -> Result<(), Error> {
+    if logs.is_empty() {
+        return Ok(());
+    }
+
+    let uri = table_uri();
+    info!(uri, "writing records to delta table");
+
+    let table = open_or_create_table(&uri).await?;
+    let batch = to_record_batch(logs)?;
+
+    DeltaOps(table)
+        .write(vec![batch])
+        .with_partition_columns(PARTITION_COLUMNS.map(String::from))
+        .await?;
+
+    info!("commit succeeded");
+    Ok(())
+}
+
+/// Delta tables are created lazily on first write so there's no separate
+/// provisioning step for local/dev tenants.
+async fn open_or_create_table(uri: &str) -> Result<DeltaTable, Error> {
+    if let Ok(table) = deltalake::open_table(uri).await {
+        return Ok(table);
+    }
+
+    info!(uri, "table not found, creating it");
+    let table = CreateBuilder::new()
+        .with_location(uri)
+        .with_columns([
+            StructField::new("tenant_id", DeltaDataType::Primitive(PrimitiveType::String), false),
+            StructField::new("log_id", DeltaDataType::Primitive(PrimitiveType::String), false),
+            StructField::new("source", DeltaDataType::Primitive(PrimitiveType::String), false),
+            StructField::new("original_text", DeltaDataType::Primitive(PrimitiveType::String), false),
+            StructField::new("modified_data", DeltaDataType::Primitive(PrimitiveType::String), false),
+            StructField::new("processed_at", DeltaDataType::Primitive(PrimitiveType::String), false),
+            StructField::new("ingest_date", DeltaDataType::Primitive(PrimitiveType::String), false),
+        ])
+        .with_partition_columns(PARTITION_COLUMNS)
+        .await?;
+    Ok(table)
+}
+
+fn to_record_batch(logs: &[ProcessedLog]) -> Result<RecordBatch, Error> {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("tenant_id", DataType::Utf8, false),
+        Field::new("log_id", DataType::Utf8, false),
+        Field::new("source", DataType::Utf8, false),
+        Field::new("original_text", DataType::Utf8, false),
+        Field::new("modified_data", DataType::Utf8, false),
+        Field::new("processed_at", DataType::Utf8, false),
+        Field::new("ingest_date", DataType::Utf8, false),
+    ]));
+
+    let ingest_dates: Vec<&str> = logs.iter().map(|l| ingest_date_of(&l.processed_at)).collect();
+
+    RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(StringArray::from_iter_values(logs.iter().map(|l| l.tenant_id.as_str()))),
+            Arc::new(StringArray::from_iter_values(logs.iter().map(|l| l.log_id.as_str()))),
+            Arc::new(StringArray::from_iter_values(logs.iter().map(|l| l.source.as_str()))),
+            Arc::new(StringArray::from_iter_values(logs.iter().map(|l| l.original_text.as_str()))),
+            Arc::new(StringArray::from_iter_values(logs.iter().map(|l| l.modified_data.as_str()))),
+            Arc::new(StringArray::from_iter_values(logs.iter().map(|l| l.processed_at.as_str()))),
+            Arc::new(StringArray::from_iter_values(ingest_dates)),
+        ],
+    )
+    .map_err(Error::from)
+}
+
+/// `processed_at` is an RFC3339 timestamp (`2026-07-26T12:34:56.789+00:00`);
+/// the partition is just its date component.
+fn ingest_date_of(processed_at: &str) -> &str {
+    processed_at.split('T').next().unwrap_or(processed_at)
+}
@@ -1,14 +1,39 @@
-use std::{collections::HashMap};
+use std::collections::{HashMap, HashSet};
 
+#[cfg(not(feature = "standalone"))]
 use aws_lambda_events::event::sqs::SqsEvent;
 use tokio::time::{sleep, Duration};
-use aws_sdk_dynamodb::types::AttributeValue;
+use aws_sdk_dynamodb::types::{AttributeValue, PutRequest, WriteRequest};
+use base64::Engine;
 use chrono::Utc;
-use lambda_runtime::{run, service_fn, Error, LambdaEvent};
-use regex::Regex;
+#[cfg(not(feature = "standalone"))]
+use lambda_runtime::{run, service_fn, LambdaEvent};
+use lambda_runtime::Error;
+use prost::Message as _;
 use serde::{Deserialize, Serialize};
+use tokio::sync::OnceCell;
+use tracing::{error, info, instrument, warn};
 use uuid::Uuid;
 
+mod delta_sink;
+mod redact;
+#[cfg(feature = "standalone")]
+mod standalone;
+
+use redact::Redactor;
+
+/// `BatchWriteItem` accepts at most 25 items per call.
+const MAX_BATCH_WRITE_ITEMS: usize = 25;
+/// Number of times to retry `UnprocessedItems` (or a failed call) before
+/// giving up on an item and reporting it as a batch item failure.
+const MAX_RETRY_ATTEMPTS: u32 = 5;
+
+/// The DynamoDB client and its backing AWS config are expensive to build
+/// (a config load does credential/region resolution), so we build them once
+/// per execution environment and reuse them across warm invocations instead
+/// of rebuilding on every message.
+static DDB_CLIENT: OnceCell<aws_sdk_dynamodb::Client> = OnceCell::const_new();
+
 /// NormalizedLog: Matches the structure sent by the ingest lambda
 /// This is the format of messages we receive from SQS
 #[derive(Debug, Clone)]
@@ -22,119 +47,221 @@ struct NormalizedLog {
     metadata: Option<HashMap<String, String>>,
 }
 
+impl From<proto::NormalizedLog> for NormalizedLog {
+    fn from(log: proto::NormalizedLog) -> Self {
+        NormalizedLog {
+            tenant_id: log.tenant_id,
+            text: log.text,
+            source: log.source,
+            timestamp: log.timestamp,
+            tags: (!log.tags.is_empty()).then_some(log.tags),
+            metadata: (!log.metadata.is_empty()).then_some(log.metadata),
+        }
+    }
+}
+
 /// ProcessedLog: The structure we store in DynamoDB
 /// Represents the final processed log with redactions and metadata
 #[derive(Debug, Clone)]
 #[derive(Serialize)]
-struct ProcessedLog {
-    tenant_id: String, // DynamoDB Partition Key
-    log_id: String, // DynamoDB Sort Key
-    source: String, // e.g. "json", "plaintext"
-    original_text: String, // The unmodified text from the log
-    modified_data: String, // Text with phone numbers redacted
-    processed_at: String, // ISO8601 timestamp of when we processed it
+pub(crate) struct ProcessedLog {
+    pub(crate) tenant_id: String, // DynamoDB Partition Key / Delta partition column
+    pub(crate) log_id: String, // DynamoDB Sort Key
+    pub(crate) source: String, // e.g. "json", "plaintext"
+    pub(crate) original_text: String, // The unmodified text from the log
+    pub(crate) modified_data: String, // Text with PII redacted
+    pub(crate) redaction_counts: HashMap<String, u32>, // how many of each entity type were redacted, for tenant auditing
+    pub(crate) processed_at: String, // ISO8601 timestamp of when we processed it
+}
+
+/// A single failed message, identified by its SQS `messageId`.
+///
+/// Returning these (instead of swallowing the error) tells the Lambda
+/// event source mapping to leave that message on the queue so it can be
+/// retried or routed to a DLQ, instead of deleting the whole batch.
+///
+/// Only used by the real Lambda `handler`; the `standalone` poller deletes
+/// messages itself instead of returning this shape.
+#[cfg(not(feature = "standalone"))]
+#[derive(Debug, Serialize)]
+struct BatchItemFailure {
+    #[serde(rename = "itemIdentifier")]
+    item_identifier: String,
+}
+
+/// The shape the SQS event source mapping expects back when
+/// `ReportBatchItemFailures` is enabled on the trigger.
+#[cfg(not(feature = "standalone"))]
+#[derive(Debug, Serialize, Default)]
+struct SqsBatchResponse {
+    #[serde(rename = "batchItemFailures")]
+    batch_item_failures: Vec<BatchItemFailure>,
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Error> {
-    println!("WORKER: starting up runtime");
-    let result = run(service_fn(handler)).await;
-    println!("WORKER: shutting down runtime (result: {:?})", result);
-    result
+    tracing_subscriber::fmt()
+        .json()
+        .with_current_span(true)
+        .with_span_list(true)
+        .with_target(false)
+        .init();
+
+    #[cfg(feature = "standalone")]
+    {
+        standalone::run().await
+    }
+
+    #[cfg(not(feature = "standalone"))]
+    {
+        info!("starting up runtime");
+        let result = run(service_fn(handler)).await;
+        info!(?result, "shutting down runtime");
+        result
+    }
 }
 
 /// this is called by AWS Lambda runtime when SQS messages arrive.
 /// extract the SQS records from the event
-/// iterate and process each record in the batch
-/// (Lambda runtime will auto-delete messages from SQS on success)
-async fn handler(event: LambdaEvent<SqsEvent>) -> Result<(), Error> {
-    println!("HANDLER: received {} SQS record(s)", event.payload.records.len());
+/// iterate and process each record in the batch, collecting the
+/// `messageId` of any record that fails to deserialize or process
+/// (Lambda runtime will auto-delete everything NOT in `batchItemFailures`)
+#[cfg(not(feature = "standalone"))]
+#[instrument(skip_all, fields(batch_size = event.payload.records.len()))]
+async fn handler(event: LambdaEvent<SqsEvent>) -> Result<SqsBatchResponse, Error> {
+    info!("received SQS batch");
+    let mut response = SqsBatchResponse::default();
+    let mut message_ids = Vec::new();
+    let mut logs = Vec::new();
+
     for (i, record) in event.payload.records.into_iter().enumerate() {
-        println!("HANDLER: processing record #{i}"
-    );
+        let message_id = record.message_id.clone();
+
         // get body, skip if missing
         let body = match record.body {
-            Some(b) => {
-                println!("HANDLER: record #{i} has body length {}", b.len());
-                b
-            }
-
+            Some(b) => b,
             None => {
-                eprintln!("SQS record missing body");
+                warn!(record = i, "SQS record missing body");
+                push_failure(&mut response, message_id);
                 continue;
             }
         };
 
-        // deserialize, skip if invalid json
-        let log = match serde_json::from_str::<NormalizedLog>(&body) {
-            Ok(l) => {
-                println!(
-                    "HANDLER: record #{i} deserialized successfully (tenant_id = {})",
-                    l.tenant_id
-                );
-                l
+        // deserialize + simulate/redact, report as failed but continue the batch;
+        // the DynamoDB write itself happens once, after the batch so it can go
+        // through BatchWriteItem instead of one PutItem per record
+        match process_record(&body).await {
+            Ok(processed_log) => {
+                message_ids.push(message_id);
+                logs.push(processed_log);
             }
             Err(e) => {
-                eprintln!("Error deserializing message {:?}", e);
-                continue;
+                error!(record = i, error = ?e, "failed to process record");
+                push_failure(&mut response, message_id);
             }
-        };
+        }
+    }
 
-        // process, log error but continue the batch
-        if let Err(e) = process_message(log).await {
-            eprintln!("Error processing message: {:?}", e)
+    // `logs` and `message_ids` only grow together (on the `Ok` arm above), so
+    // index `i` means the same record in both — map sink failures back to
+    // messages positionally instead of by `(tenant_id, log_id)`, which can
+    // collide when a batch has duplicate keys.
+    let failed_indices = write_sinks(logs).await;
+    for (i, message_id) in message_ids.into_iter().enumerate() {
+        if failed_indices.contains(&i) {
+            push_failure(&mut response, message_id);
         }
     }
-    Ok(())
-}
 
-/// for the log, we first grab length, and sleep for simulated time
-async fn process_message(log: NormalizedLog) -> Result<(), Error> {
-    println!(
-        "PROCESS: start tenant={} text_len={}",
-        log.tenant_id,
-        log.text.len()
+    info!(
+        failed = response.batch_item_failures.len(),
+        "finished processing batch"
     );
+    Ok(response)
+}
 
+/// Record a message as failed, as long as SQS actually gave us a `messageId`
+/// to report back (it always should, but the field is `Option` on the event type).
+#[cfg(not(feature = "standalone"))]
+fn push_failure(response: &mut SqsBatchResponse, message_id: Option<String>) {
+    match message_id {
+        Some(item_identifier) => response.batch_item_failures.push(BatchItemFailure { item_identifier }),
+        None => warn!("SQS record failed but has no messageId to report as a batch item failure"),
+    }
+}
+
+/// deserialize a single SQS record body and run it through `process_message`.
+///
+/// Shared between the Lambda `handler` and the `standalone` polling loop so
+/// both entry points process a message exactly the same way. Does NOT write
+/// to DynamoDB itself; callers batch the returned `ProcessedLog`s and write
+/// them together via `write_sinks`.
+#[instrument(skip(body))]
+pub(crate) async fn process_record(body: &str) -> Result<ProcessedLog, Error> {
+    let log = decode_normalized_log(body)?;
+    process_message(log).await
+}
+
+/// Decode an SQS record body into a `NormalizedLog`.
+///
+/// Bodies prefixed with `proto::CONTENT_PREFIX` are base64+protobuf, the
+/// compact format the ingest lambda sends today. Anything else is assumed to
+/// be the legacy JSON format, so in-flight messages survive a deploy that
+/// switches the ingest lambda over to protobuf mid-migration.
+#[instrument(skip(body))]
+fn decode_normalized_log(body: &str) -> Result<NormalizedLog, Error> {
+    match body.strip_prefix(proto::CONTENT_PREFIX) {
+        Some(encoded) => {
+            let bytes = base64::engine::general_purpose::STANDARD.decode(encoded).map_err(|e| {
+                error!(error = ?e, "failed to base64-decode message");
+                e
+            })?;
+            let log = proto::NormalizedLog::decode(bytes.as_slice()).map_err(|e| {
+                error!(error = ?e, "failed to decode protobuf message");
+                e
+            })?;
+            Ok(log.into())
+        }
+        None => serde_json::from_str::<NormalizedLog>(body).map_err(|e| {
+            error!(error = ?e, "failed to deserialize legacy JSON message");
+            Error::from(e)
+        }),
+    }
+}
+
+/// for the log, we first grab length, and sleep for simulated time
+#[instrument(
+    skip(log),
+    fields(tenant_id = %log.tenant_id, log_id = tracing::field::Empty, text_len = log.text.len())
+)]
+async fn process_message(log: NormalizedLog) -> Result<ProcessedLog, Error> {
     // first sleep
     simulate_heavy_processing(&log.text).await;
-    println!("PROCESS: finished simulated processing");
 
-    // redact the numbers like we see in the example
-    let modified_data = redact_phone_numbers(&log.text);
-    println!(
-        "PROCESS: redacted text from '{}' -> '{}'",
-        log.text, modified_data
-    );
+    // run the tenant's configured PII detectors over the text
+    let (modified_data, redaction_counts) = Redactor::from_env().redact(&log.text);
+    info!(?redaction_counts, "redacted text");
 
     let log_id = log.metadata
         .as_ref()
         .and_then(|m| m.get("log_id"))
         .cloned()
         .unwrap_or_else(|| Uuid::nil().to_string());
-    
+    tracing::Span::current().record("log_id", tracing::field::display(&log_id));
+
     let processed_log = ProcessedLog {
         tenant_id: log.tenant_id,
         log_id,
         source: log.source.unwrap_or_else(|| "unknown".to_string()),
         original_text: log.text,
         modified_data,
+        redaction_counts,
         processed_at: Utc::now().to_rfc3339(),
     };
 
-    println!(
-        "PROCESS: prepared ProcessedLog for tenant={} log_id={}",
-        processed_log.tenant_id, processed_log.log_id
-    );
-
-    // try to write to DynamoDB
-    println!("PROCESS: saving to DynamoDB...");
-    match save_to_dynamodb(processed_log).await {
-        Ok(_) => println!("PROCESS: DynamoDB write successful"),
-        Err(e) => eprintln!("PROCESS: DynamoDB write FAILED: {:?}", e),
-    }
+    info!("prepared processed log");
 
-    Ok(())
+    Ok(processed_log)
 }
 
 /// Count the characters in the text
@@ -143,83 +270,347 @@ async fn process_message(log: NormalizedLog) -> Result<(), Error> {
 async fn simulate_heavy_processing(text: &str) {
     let char_count = text.chars().count() as u64;
     let sleep_ms = char_count * 50;
-    println!(
-        "SIMULATE: sleeping for {} ms ({} chars)",
-        sleep_ms, char_count
-    );
     sleep(Duration::from_millis(sleep_ms)).await;
 }
 
-// parse text, find phone numbers via regex (?)
-// replace that index in the text with "[REDACTED]"
-fn redact_phone_numbers(text: &str) -> String {
-    let re = Regex::new(r"\b(?:\d{3}-\d{4}|\d{3}-\d{3}-\d{4})\b").unwrap();
-    let result = re.replace_all(text, "[REDACTED]").to_string();
-    println!("REDACT: before='{}' after='{}'", text, result);
-    result
+/// Which sink(s) a batch of `ProcessedLog`s should be written to, as chosen
+/// by the `SINK_MODE` env var (`"dynamodb"` [default], `"delta"`, `"both"`).
+/// A Delta write failure fails the whole batch it was attempted against
+/// (it's one transaction), so every record in that attempt is reported back
+/// as a batch item failure. Failures are indices into `logs` rather than
+/// `(tenant_id, log_id)` keys, since those keys aren't guaranteed unique
+/// within a batch (see `dedupe_by_key`).
+///
+/// In `"both"` mode, DynamoDB is written first and only the records that
+/// succeeded there are appended to Delta. A record that fails DynamoDB gets
+/// redelivered by SQS and goes through both sinks together on retry, so
+/// appending it to Delta here too would leave a duplicate row behind in the
+/// (non-idempotent) Delta table once that retry succeeds.
+#[instrument(skip(logs), fields(batch_size = logs.len()))]
+pub(crate) async fn write_sinks(logs: Vec<ProcessedLog>) -> HashSet<usize> {
+    let mode = std::env::var("SINK_MODE").unwrap_or_else(|_| "dynamodb".to_string());
+
+    if mode == "delta" {
+        return match delta_sink::write_batch(&logs).await {
+            Ok(()) => HashSet::new(),
+            Err(e) => {
+                error!(error = ?e, "delta batch write failed, failing every record in the batch");
+                (0..logs.len()).collect()
+            }
+        };
+    }
+
+    if mode == "both" {
+        // Dedupe the Delta candidate set the same way `save_batch_to_dynamodb`
+        // dedupes its own — otherwise a duplicate `(tenant_id, log_id)` key
+        // ends up with one row in DynamoDB (last write wins) but N rows in
+        // the append-only Delta table.
+        let delta_candidates = dedupe_by_key(logs.clone().into_iter().enumerate().collect());
+        let mut failed = save_batch_to_dynamodb(logs).await;
+
+        let (delta_indices, delta_logs): (Vec<usize>, Vec<ProcessedLog>) = delta_candidates
+            .into_iter()
+            .filter(|(i, _)| !failed.contains(i))
+            .unzip();
+
+        if !delta_logs.is_empty() {
+            if let Err(e) = delta_sink::write_batch(&delta_logs).await {
+                error!(error = ?e, "delta batch write failed for the dynamodb-succeeded subset");
+                failed.extend(delta_indices);
+            }
+        }
+
+        return failed;
+    }
+
+    save_batch_to_dynamodb(logs).await
 }
 
-/// save processed log to DynamoDB with tenant isolation
+/// Build (or return the already-built) DynamoDB client for this execution
+/// environment. Built once per cold start and reused across every warm
+/// invocation that follows, instead of re-resolving AWS config per message.
+async fn dynamodb_client() -> &'static aws_sdk_dynamodb::Client {
+    DDB_CLIENT
+        .get_or_init(|| async {
+            info!("initializing AWS config + client (cold start)");
+            let config = aws_config::load_defaults(aws_config::BehaviorVersion::latest()).await;
+
+            // allow pointing at a local DynamoDB / LocalStack endpoint (used by `standalone` mode)
+            let mut client_builder = aws_sdk_dynamodb::config::Builder::from(&config);
+            if let Ok(endpoint) = std::env::var("DYNAMODB_ENDPOINT") {
+                info!(endpoint, "overriding DynamoDB endpoint");
+                client_builder = client_builder.endpoint_url(endpoint);
+            }
+            aws_sdk_dynamodb::Client::from_conf(client_builder.build())
+        })
+        .await
+}
+
+/// Write a batch of processed logs to DynamoDB with tenant isolation.
 ///
 /// DynamoDB Schema:
 /// - Partition Key: tenant_id (String)
 /// - Sort Key: log_id (String)
-async fn save_to_dynamodb(log: ProcessedLog) -> Result<(), Error> {
-    // Get table name from environment variable
-    let table_name = std::env::var("TABLE_NAME")
-        .unwrap_or_else(|_| "processed_logs".to_string());
-    println!("DDB: using table '{table_name}'");
-    // init AWS config and DynamoDB client
-    let config = aws_config::load_defaults(aws_config::BehaviorVersion::latest()).await;
-    println!("DDB: AWS config loaded");
-
-    let client = aws_sdk_dynamodb::Client::new(&config);
-    println!("DDB: client initialized");
-
-    // build the item as a hashmap of AttributeValues
+///
+/// Writes go out in groups of up to 25 via `BatchWriteItem`. Any
+/// `UnprocessedItems` DynamoDB hands back (e.g. from throttling) are retried
+/// with exponential backoff; items still unprocessed after
+/// `MAX_RETRY_ATTEMPTS` are returned to the caller as indices into `logs`, so
+/// they can be reported as batch item failures instead of silently lost.
+#[instrument(skip(logs), fields(batch_size = logs.len()))]
+pub(crate) async fn save_batch_to_dynamodb(logs: Vec<ProcessedLog>) -> HashSet<usize> {
+    if logs.is_empty() {
+        return HashSet::new();
+    }
+
+    // `BatchWriteItem` rejects a request containing two items with the same
+    // key, so dedupe before chunking (duplicates are realistic: every record
+    // missing `log_id` metadata falls back to the same `Uuid::nil()`). Each
+    // surviving entry keeps its original index so a failure maps back to the
+    // right record instead of colliding with another record on a shared key.
+    let indexed = dedupe_by_key(logs.into_iter().enumerate().collect());
+
+    let table_name = std::env::var("TABLE_NAME").unwrap_or_else(|_| "processed_logs".to_string());
+    let client = dynamodb_client().await;
+
+    let mut permanently_failed = HashSet::new();
+
+    for chunk in indexed.chunks(MAX_BATCH_WRITE_ITEMS) {
+        info!(table = %table_name, chunk_size = chunk.len(), "writing batch");
+        // Keys are unique within a chunk post-dedupe, so this map recovers the
+        // original index an `UnprocessedItems` entry belongs to.
+        let key_to_index: HashMap<(String, String), usize> = chunk
+            .iter()
+            .map(|(i, log)| ((log.tenant_id.clone(), log.log_id.clone()), *i))
+            .collect();
+        let mut pending: Vec<WriteRequest> = chunk.iter().map(|(_, log)| to_write_request(log)).collect();
+        let mut attempt = 0u32;
+
+        while !pending.is_empty() {
+            let mut request_items = HashMap::new();
+            request_items.insert(table_name.clone(), pending.clone());
+
+            let unprocessed = match client
+                .batch_write_item()
+                .set_request_items(Some(request_items))
+                .send()
+                .await
+            {
+                Ok(output) => output
+                    .unprocessed_items
+                    .and_then(|mut m| m.remove(&table_name))
+                    .unwrap_or_default(),
+                Err(e) => {
+                    error!(error = ?e, "BatchWriteItem error");
+                    pending.clone()
+                }
+            };
+
+            if unprocessed.is_empty() {
+                info!("batch write succeeded");
+                break;
+            }
+
+            attempt += 1;
+            if attempt > MAX_RETRY_ATTEMPTS {
+                error!(
+                    unprocessed = unprocessed.len(),
+                    attempts = attempt - 1,
+                    "item(s) permanently unprocessed"
+                );
+                permanently_failed.extend(
+                    unprocessed
+                        .iter()
+                        .filter_map(write_request_key)
+                        .filter_map(|key| key_to_index.get(&key).copied()),
+                );
+                break;
+            }
+
+            let backoff_ms = 100u64 * 2u64.pow(attempt - 1);
+            warn!(
+                unprocessed = unprocessed.len(),
+                backoff_ms,
+                attempt,
+                max_attempts = MAX_RETRY_ATTEMPTS,
+                "item(s) unprocessed, retrying"
+            );
+            sleep(Duration::from_millis(backoff_ms)).await;
+            pending = unprocessed;
+        }
+    }
+
+    permanently_failed
+}
+
+/// Keep only the last `ProcessedLog` for each `(tenant_id, log_id)` key,
+/// matching the last-write-wins semantics the baseline single-item `PutItem`
+/// calls had. A dropped earlier duplicate is not reported as a failure — it
+/// was superseded, not lost. Each entry carries the original index it had
+/// before dedupe, so a write failure for the surviving entry can still be
+/// reported against the right record.
+fn dedupe_by_key(logs: Vec<(usize, ProcessedLog)>) -> Vec<(usize, ProcessedLog)> {
+    let mut last_position_for_key: HashMap<(String, String), usize> = HashMap::new();
+    for (position, (_, log)) in logs.iter().enumerate() {
+        last_position_for_key.insert((log.tenant_id.clone(), log.log_id.clone()), position);
+    }
+
+    logs.into_iter()
+        .enumerate()
+        .filter(|(position, (_, log))| {
+            last_position_for_key.get(&(log.tenant_id.clone(), log.log_id.clone())) == Some(position)
+        })
+        .map(|(_, entry)| entry)
+        .collect()
+}
+
+/// Turn one `ProcessedLog` into the `PutRequest` shape `BatchWriteItem` expects.
+fn to_write_request(log: &ProcessedLog) -> WriteRequest {
     let mut item = HashMap::new();
-    item.insert(
-        "tenant_id".to_string(),
-        AttributeValue::S(log.tenant_id),
-    );
-    item.insert(
-        "log_id".to_string(),
-        AttributeValue::S(log.log_id),
-    );
-    item.insert(
-        "source".to_string(),
-        AttributeValue::S(log.source),
-    );
+    item.insert("tenant_id".to_string(), AttributeValue::S(log.tenant_id.clone()));
+    item.insert("log_id".to_string(), AttributeValue::S(log.log_id.clone()));
+    item.insert("source".to_string(), AttributeValue::S(log.source.clone()));
     item.insert(
         "original_text".to_string(),
-        AttributeValue::S(log.original_text),
+        AttributeValue::S(log.original_text.clone()),
     );
     item.insert(
         "modified_data".to_string(),
-        AttributeValue::S(log.modified_data),
+        AttributeValue::S(log.modified_data.clone()),
+    );
+    item.insert(
+        "redaction_counts".to_string(),
+        AttributeValue::M(
+            log.redaction_counts
+                .iter()
+                .map(|(entity, count)| (entity.clone(), AttributeValue::N(count.to_string())))
+                .collect(),
+        ),
     );
     item.insert(
         "processed_at".to_string(),
-        AttributeValue::S(log.processed_at),
+        AttributeValue::S(log.processed_at.clone()),
     );
 
-    // do the put item operation
-    match client
-        .put_item()
-        .table_name(table_name)
-        .set_item(Some(item))
-        .send()
-        .await
-    {
-        Ok(_) => {
-            println!("DDB: PutItem succeeded");
-            Ok(())
-        }
-        Err(e) => {
-            eprintln!("DDB: PutItem error: {:?}", e);
-            Err(Box::from(format!("Failed to save to DynamoDB: {}", e)) as Box<
-                dyn std::error::Error + Send + Sync,
-            >)
+    WriteRequest::builder()
+        .set_put_request(Some(PutRequest::builder().set_item(Some(item)).build().expect("item is always set")))
+        .build()
+}
+
+/// Recover the `(tenant_id, log_id)` key from an unprocessed `WriteRequest`,
+/// so a permanently-failed item can be mapped back to its SQS `messageId`.
+fn write_request_key(request: &WriteRequest) -> Option<(String, String)> {
+    let item = request.put_request()?.item();
+    let tenant_id = item.get("tenant_id")?.as_s().ok()?.clone();
+    let log_id = item.get("log_id")?.as_s().ok()?.clone();
+    Some((tenant_id, log_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn processed_log(tenant_id: &str, log_id: &str) -> ProcessedLog {
+        ProcessedLog {
+            tenant_id: tenant_id.to_string(),
+            log_id: log_id.to_string(),
+            source: "test".to_string(),
+            original_text: "text".to_string(),
+            modified_data: "text".to_string(),
+            redaction_counts: HashMap::new(),
+            processed_at: "2026-07-26T00:00:00+00:00".to_string(),
         }
     }
+
+    #[test]
+    fn write_request_key_round_trips_through_to_write_request() {
+        let log = processed_log("tenant-a", "log-1");
+        let request = to_write_request(&log);
+        assert_eq!(
+            write_request_key(&request),
+            Some(("tenant-a".to_string(), "log-1".to_string()))
+        );
+    }
+
+    #[test]
+    fn a_chunk_of_25_logs_produces_a_single_chunk() {
+        let logs: Vec<ProcessedLog> = (0..MAX_BATCH_WRITE_ITEMS)
+            .map(|i| processed_log("tenant-a", &i.to_string()))
+            .collect();
+        assert_eq!(logs.chunks(MAX_BATCH_WRITE_ITEMS).count(), 1);
+    }
+
+    #[test]
+    fn one_more_than_a_chunk_spills_into_a_second_chunk() {
+        let logs: Vec<ProcessedLog> = (0..MAX_BATCH_WRITE_ITEMS + 1)
+            .map(|i| processed_log("tenant-a", &i.to_string()))
+            .collect();
+        let chunks: Vec<_> = logs.chunks(MAX_BATCH_WRITE_ITEMS).collect();
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[1].len(), 1);
+    }
+
+    #[test]
+    fn dedupe_by_key_keeps_only_the_last_log_for_a_duplicate_key() {
+        let mut first = processed_log("tenant-a", "log-1");
+        first.modified_data = "stale".to_string();
+        let mut second = processed_log("tenant-a", "log-1");
+        second.modified_data = "fresh".to_string();
+        let other = processed_log("tenant-a", "log-2");
+
+        let deduped = dedupe_by_key(vec![(0, first), (1, second), (2, other)]);
+
+        assert_eq!(deduped.len(), 2);
+        let (kept_index, kept) = deduped
+            .iter()
+            .find(|(_, l)| l.log_id == "log-1")
+            .expect("log-1 survives dedupe");
+        assert_eq!(*kept_index, 1);
+        assert_eq!(kept.modified_data, "fresh");
+    }
+
+    #[test]
+    fn dedupe_by_key_is_a_noop_without_duplicates() {
+        let indexed = vec![
+            (0, processed_log("tenant-a", "log-1")),
+            (1, processed_log("tenant-a", "log-2")),
+        ];
+        let deduped = dedupe_by_key(indexed.clone());
+        assert_eq!(deduped.len(), indexed.len());
+    }
+
+    #[test]
+    fn decode_normalized_log_round_trips_protobuf_body() {
+        let log = proto::NormalizedLog {
+            tenant_id: "tenant-a".to_string(),
+            text: "hello".to_string(),
+            source: Some("json".to_string()),
+            timestamp: None,
+            tags: Vec::new(),
+            metadata: HashMap::new(),
+        };
+        let body = format!(
+            "{}{}",
+            proto::CONTENT_PREFIX,
+            base64::engine::general_purpose::STANDARD.encode(log.encode_to_vec())
+        );
+
+        let decoded = decode_normalized_log(&body).expect("protobuf body decodes");
+        assert_eq!(decoded.tenant_id, "tenant-a");
+        assert_eq!(decoded.text, "hello");
+    }
+
+    #[test]
+    fn decode_normalized_log_falls_back_to_legacy_json() {
+        let body = r#"{"tenant_id":"tenant-b","text":"hi"}"#;
+
+        let decoded = decode_normalized_log(body).expect("legacy JSON body decodes");
+        assert_eq!(decoded.tenant_id, "tenant-b");
+        assert_eq!(decoded.text, "hi");
+    }
+
+    #[test]
+    fn decode_normalized_log_rejects_garbage_body() {
+        assert!(decode_normalized_log("not json and no pb1: prefix").is_err());
+    }
 }
@@ -0,0 +1,260 @@
+//! Pluggable PII redaction engine.
+//!
+//! A `Redactor` runs an ordered set of typed `Detector`s over a log's text,
+//! each one replacing its entity type with a `[ENTITY_REDACTED]` token and
+//! reporting how many instances it found. Which detectors run is
+//! configurable via the `REDACTION_DETECTORS` env var (a JSON array of
+//! detector names, e.g. `["email","ssn"]`); the one set applies to every
+//! tenant this worker processes. Unset or unparseable falls back to phone-only,
+//! matching the baseline behavior tenants had before this engine existed.
+
+use std::collections::{HashMap, HashSet};
+
+use regex::Regex;
+
+/// A single entity type a `Redactor` can find and replace.
+trait Detector {
+    /// Short identifier used in the `REDACTION_DETECTORS` config and in the
+    /// `[ENTITY_REDACTED]` replacement token.
+    fn entity_name(&self) -> &'static str;
+
+    /// Replace every match of this entity in `text`, returning the new text
+    /// and how many replacements were made.
+    fn redact(&self, text: &str) -> (String, u32);
+}
+
+/// Most entities are a plain "find this pattern, replace every match".
+struct RegexDetector {
+    entity: &'static str,
+    pattern: &'static str,
+}
+
+impl Detector for RegexDetector {
+    fn entity_name(&self) -> &'static str {
+        self.entity
+    }
+
+    fn redact(&self, text: &str) -> (String, u32) {
+        let re = Regex::new(self.pattern).expect("redaction pattern is valid");
+        let count = re.find_iter(text).count() as u32;
+        let token = format!("[{}_REDACTED]", self.entity);
+        (re.replace_all(text, token.as_str()).to_string(), count)
+    }
+}
+
+/// Credit card numbers need a regex match *and* a Luhn checksum before
+/// they're redacted, so arbitrary 16-digit numbers aren't swept up.
+struct CreditCardDetector;
+
+impl Detector for CreditCardDetector {
+    fn entity_name(&self) -> &'static str {
+        "CREDIT_CARD"
+    }
+
+    fn redact(&self, text: &str) -> (String, u32) {
+        let re = Regex::new(r"\b\d(?:[ -]?\d){12,18}\b").expect("credit card pattern is valid");
+        let mut count = 0;
+        let result = re.replace_all(text, |caps: &regex::Captures| {
+            let matched = &caps[0];
+            let digits: String = matched.chars().filter(|c| c.is_ascii_digit()).collect();
+            if is_luhn_valid(&digits) {
+                count += 1;
+                "[CREDIT_CARD_REDACTED]".to_string()
+            } else {
+                matched.to_string()
+            }
+        });
+        (result.to_string(), count)
+    }
+}
+
+/// Luhn checksum: double every second digit counting from the right,
+/// subtract 9 from any result over 9, and require the total to be
+/// divisible by 10.
+fn is_luhn_valid(digits: &str) -> bool {
+    if !(13..=19).contains(&digits.len()) {
+        return false;
+    }
+
+    let sum: u32 = digits
+        .chars()
+        .rev()
+        .enumerate()
+        .map(|(i, c)| {
+            let d = c.to_digit(10).expect("digits is pre-filtered to ASCII digits");
+            if i % 2 == 1 {
+                let doubled = d * 2;
+                if doubled > 9 { doubled - 9 } else { doubled }
+            } else {
+                d
+            }
+        })
+        .sum();
+
+    sum % 10 == 0
+}
+
+fn all_detectors() -> Vec<(&'static str, Box<dyn Detector>)> {
+    vec![
+        (
+            "phone",
+            Box::new(RegexDetector {
+                entity: "PHONE",
+                pattern: r"\b(?:\d{3}-\d{4}|\d{3}-\d{3}-\d{4})\b",
+            }) as Box<dyn Detector>,
+        ),
+        (
+            "email",
+            Box::new(RegexDetector {
+                entity: "EMAIL",
+                pattern: r"[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}",
+            }),
+        ),
+        (
+            "ssn",
+            Box::new(RegexDetector {
+                entity: "SSN",
+                pattern: r"\b\d{3}-\d{2}-\d{4}\b",
+            }),
+        ),
+        (
+            "ip",
+            Box::new(RegexDetector {
+                entity: "IP_ADDRESS",
+                pattern: r"\b(?:(?:25[0-5]|2[0-4]\d|1?\d?\d)\.){3}(?:25[0-5]|2[0-4]\d|1?\d?\d)\b",
+            }),
+        ),
+        ("credit_card", Box::new(CreditCardDetector)),
+    ]
+}
+
+/// Runs the configured detectors over a log's text in order, chaining each
+/// detector's output into the next so overlapping patterns still see the
+/// original text for the entities ahead of them in the list.
+pub(crate) struct Redactor {
+    detectors: Vec<Box<dyn Detector>>,
+}
+
+impl Redactor {
+    /// Build a `Redactor` from the `REDACTION_DETECTORS` env var, which should
+    /// be a JSON array of detector names (`"phone"`, `"email"`, `"ssn"`,
+    /// `"ip"`, `"credit_card"`). Missing or invalid config runs phone-only,
+    /// matching the baseline behavior for tenants that haven't opted into
+    /// anything else.
+    pub(crate) fn from_env() -> Self {
+        let configured = std::env::var("REDACTION_DETECTORS")
+            .ok()
+            .and_then(|raw| serde_json::from_str::<Vec<String>>(&raw).ok());
+
+        let detectors = match configured {
+            Some(names) => {
+                let wanted: HashSet<String> = names.into_iter().collect();
+                all_detectors()
+                    .into_iter()
+                    .filter(|(name, _)| wanted.contains(*name))
+                    .map(|(_, detector)| detector)
+                    .collect()
+            }
+            None => all_detectors()
+                .into_iter()
+                .filter(|(name, _)| *name == "phone")
+                .map(|(_, detector)| detector)
+                .collect(),
+        };
+
+        Redactor { detectors }
+    }
+
+    /// Redact `text`, returning the redacted string alongside a count of how
+    /// many instances of each entity type were found.
+    pub(crate) fn redact(&self, text: &str) -> (String, HashMap<String, u32>) {
+        let mut result = text.to_string();
+        let mut counts = HashMap::new();
+
+        for detector in &self.detectors {
+            let (next, count) = detector.redact(&result);
+            result = next;
+            if count > 0 {
+                counts.insert(detector.entity_name().to_string(), count);
+            }
+        }
+
+        (result, counts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn luhn_accepts_known_valid_numbers() {
+        assert!(is_luhn_valid("4111111111111111"));
+        assert!(is_luhn_valid("4012888888881881"));
+    }
+
+    #[test]
+    fn luhn_rejects_invalid_numbers() {
+        assert!(!is_luhn_valid("4111111111111112"));
+        assert!(!is_luhn_valid("1234567890123"));
+    }
+
+    #[test]
+    fn luhn_rejects_out_of_range_lengths() {
+        assert!(!is_luhn_valid("123456789012"));
+        assert!(!is_luhn_valid("12345678901234567890"));
+    }
+
+    #[test]
+    fn credit_card_detector_redacts_valid_card_and_counts() {
+        let (redacted, count) = CreditCardDetector.redact("card: 4111111111111111 end");
+        assert_eq!(redacted, "card: [CREDIT_CARD_REDACTED] end");
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn credit_card_detector_ignores_invalid_checksum() {
+        let (redacted, count) = CreditCardDetector.redact("card: 4111111111111112 end");
+        assert_eq!(redacted, "card: 4111111111111112 end");
+        assert_eq!(count, 0);
+    }
+
+    #[test]
+    fn regex_detector_redacts_and_counts_each_match() {
+        let detector = RegexDetector {
+            entity: "PHONE",
+            pattern: r"\b(?:\d{3}-\d{4}|\d{3}-\d{3}-\d{4})\b",
+        };
+        let (redacted, count) = detector.redact("call 555-1234 or 555-123-4567");
+        assert_eq!(redacted, "call [PHONE_REDACTED] or [PHONE_REDACTED]");
+        assert_eq!(count, 2);
+    }
+
+    #[test]
+    fn redactor_defaults_to_phone_only_when_env_unset() {
+        std::env::remove_var("REDACTION_DETECTORS");
+        let (redacted, counts) = Redactor::from_env().redact("email me at a@b.com or call 555-1234");
+        assert_eq!(redacted, "email me at a@b.com or call [PHONE_REDACTED]");
+        assert_eq!(counts.get("PHONE"), Some(&1));
+        assert!(!counts.contains_key("EMAIL"));
+    }
+
+    #[test]
+    fn redactor_defaults_to_phone_only_when_env_invalid() {
+        std::env::set_var("REDACTION_DETECTORS", "not valid json");
+        let (redacted, counts) = Redactor::from_env().redact("call 555-1234");
+        std::env::remove_var("REDACTION_DETECTORS");
+        assert_eq!(redacted, "call [PHONE_REDACTED]");
+        assert_eq!(counts.get("PHONE"), Some(&1));
+    }
+
+    #[test]
+    fn redactor_runs_only_configured_detectors() {
+        std::env::set_var("REDACTION_DETECTORS", r#"["email"]"#);
+        let (redacted, counts) = Redactor::from_env().redact("email me at a@b.com or call 555-1234");
+        std::env::remove_var("REDACTION_DETECTORS");
+        assert_eq!(redacted, "email me at [EMAIL_REDACTED] or call 555-1234");
+        assert_eq!(counts.get("EMAIL"), Some(&1));
+        assert!(!counts.contains_key("PHONE"));
+    }
+}
@@ -0,0 +1,130 @@
+//! Non-Lambda entry point for local development.
+//!
+//! Loads configuration from a `.env` file and the CLI, then long-polls the
+//! SQS queue directly instead of waiting for the Lambda event source
+//! mapping to invoke us. Each message goes through the same
+//! `crate::process_record` path the real `handler` uses, so local runs
+//! exercise the exact ingest -> SQS -> worker -> DynamoDB pipeline.
+
+use clap::Parser;
+use lambda_runtime::Error;
+use tracing::{error, info, warn};
+
+use crate::{process_record, write_sinks, ProcessedLog};
+
+/// CLI / env config for running the worker outside of Lambda.
+///
+/// Every flag except `--poll-wait-seconds` can also be supplied via `.env` or
+/// the environment (loaded with the matching `env` key below), so `cargo run --features standalone`
+/// works against a local queue/table with no flags at all.
+#[derive(Debug, Parser)]
+#[command(name = "worker", about = "Run the worker as a standalone SQS poller")]
+struct Args {
+    /// URL of the SQS queue to long-poll.
+    #[arg(long, env = "QUEUE_URL")]
+    queue_url: String,
+
+    /// DynamoDB table to write processed logs to.
+    #[arg(long, env = "TABLE_NAME", default_value = "processed_logs")]
+    table_name: String,
+
+    /// Override the SQS endpoint (e.g. a LocalStack container).
+    #[arg(long, env = "SQS_ENDPOINT")]
+    sqs_endpoint: Option<String>,
+
+    /// Override the DynamoDB endpoint (e.g. a local DynamoDB / LocalStack container).
+    #[arg(long, env = "DYNAMODB_ENDPOINT")]
+    dynamodb_endpoint: Option<String>,
+
+    /// Long-poll wait time, in seconds (SQS caps this at 20).
+    #[arg(long, default_value_t = 20)]
+    poll_wait_seconds: i32,
+}
+
+/// Entry point invoked by `main` when the `standalone` feature is enabled.
+pub async fn run() -> Result<(), Error> {
+    if let Err(e) = dotenvy::dotenv() {
+        info!(error = %e, "no .env file loaded");
+    }
+    let args = Args::parse();
+
+    // `save_batch_to_dynamodb` reads these from the environment, so set them here
+    // once rather than threading an endpoint override through every call.
+    std::env::set_var("TABLE_NAME", &args.table_name);
+    if let Some(endpoint) = &args.dynamodb_endpoint {
+        std::env::set_var("DYNAMODB_ENDPOINT", endpoint);
+    }
+
+    let mut config_loader = aws_config::defaults(aws_config::BehaviorVersion::latest());
+    if let Some(endpoint) = &args.sqs_endpoint {
+        config_loader = config_loader.endpoint_url(endpoint);
+    }
+    let config = config_loader.load().await;
+    let sqs_client = aws_sdk_sqs::Client::new(&config);
+
+    info!(queue_url = %args.queue_url, table = %args.table_name, "long-polling queue");
+
+    loop {
+        let received = sqs_client
+            .receive_message()
+            .queue_url(&args.queue_url)
+            .wait_time_seconds(args.poll_wait_seconds)
+            .max_number_of_messages(10)
+            .send()
+            .await?;
+
+        let messages = received.messages.unwrap_or_default();
+        info!(count = messages.len(), "received messages");
+        if messages.is_empty() {
+            continue;
+        }
+
+        // deserialize + process each message, same as the Lambda handler, then
+        // write the whole batch to DynamoDB together via BatchWriteItem
+        let mut receipt_handles = Vec::new();
+        let mut logs: Vec<ProcessedLog> = Vec::new();
+        for message in messages {
+            let Some(body) = message.body.as_deref() else {
+                warn!("message missing body, skipping");
+                continue;
+            };
+            let Some(receipt_handle) = message.receipt_handle else {
+                warn!("message missing receipt handle, skipping");
+                continue;
+            };
+
+            match process_record(body).await {
+                Ok(processed_log) => {
+                    receipt_handles.push(receipt_handle);
+                    logs.push(processed_log);
+                }
+                Err(e) => {
+                    error!(error = ?e, "failed to process message, leaving on queue");
+                }
+            }
+        }
+
+        // `logs` and `receipt_handles` only grow together (on the `Ok` arm
+        // above), so index `i` means the same message in both — map sink
+        // failures back to messages positionally instead of by
+        // `(tenant_id, log_id)`, which can collide when a batch has
+        // duplicate keys.
+        let failed_indices = write_sinks(logs).await;
+
+        for (i, receipt_handle) in receipt_handles.into_iter().enumerate() {
+            if failed_indices.contains(&i) {
+                warn!("leaving unprocessed message on queue");
+                continue;
+            }
+            if let Err(e) = sqs_client
+                .delete_message()
+                .queue_url(&args.queue_url)
+                .receipt_handle(receipt_handle)
+                .send()
+                .await
+            {
+                error!(error = ?e, "failed to delete message");
+            }
+        }
+    }
+}